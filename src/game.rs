@@ -0,0 +1,220 @@
+// A playable two-player session wrapped around `GameState`: turn
+// enforcement, a lifecycle state machine, and AI-assisted hints drawn
+// from a solved `Tree`.
+
+use std::fmt;
+
+use crate::{nav, Action, GameState, Player, Progress, Tree};
+
+/// Lifecycle of a single match, from matchmaking through to a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum State {
+    WaitingForOpponent,
+    YourMove,
+    OpponentMove,
+    YouWon,
+    OpponentWon,
+    Draw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GameError {
+    NotYourTurn,
+    InvalidMove,
+    GameFinished,
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::NotYourTurn => write!(f, "it is not your turn"),
+            GameError::InvalidMove => write!(f, "that cell is already taken"),
+            GameError::GameFinished => write!(f, "the game has already finished"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+pub(crate) struct Game {
+    state: GameState,
+    lifecycle: State,
+}
+
+impl Game {
+    /// Starts a new match on an empty board, awaiting an opponent.
+    pub(crate) fn create() -> Game {
+        Game {
+            state: GameState::initial(),
+            lifecycle: State::WaitingForOpponent,
+        }
+    }
+
+    /// The opponent joins, opening the board for the first move.
+    pub(crate) fn join(&mut self) -> State {
+        if self.lifecycle == State::WaitingForOpponent {
+            self.lifecycle = State::YourMove;
+        }
+        self.lifecycle
+    }
+
+    pub(crate) fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub(crate) fn lifecycle(&self) -> State {
+        self.lifecycle
+    }
+
+    /// Applies `action` on behalf of `by`, enforcing turn order (both the
+    /// lifecycle phase and that `by` is actually the side to move) and cell
+    /// occupancy, and advances the lifecycle to reflect the resulting
+    /// position.
+    pub(crate) fn make_move(&mut self, by: Player, action: Action) -> Result<State, GameError> {
+        match self.lifecycle {
+            State::YouWon | State::OpponentWon | State::Draw => {
+                return Err(GameError::GameFinished)
+            }
+            State::WaitingForOpponent => return Err(GameError::NotYourTurn),
+            State::YourMove | State::OpponentMove => {}
+        }
+        if by != self.state.turn {
+            return Err(GameError::NotYourTurn);
+        }
+        if self.state.board[action.row][action.col].is_some() {
+            return Err(GameError::InvalidMove);
+        }
+
+        self.state = self.state.next(action);
+        self.lifecycle = match self.state.progress() {
+            Progress::Win(Player::You) => State::YouWon,
+            Progress::Win(Player::Opponent) => State::OpponentWon,
+            Progress::Draw => State::Draw,
+            Progress::Ongoing => match self.state.turn {
+                Player::You => State::YourMove,
+                Player::Opponent => State::OpponentMove,
+            },
+        };
+        Ok(self.lifecycle)
+    }
+
+    /// The optimal actions for the side to move in the current position,
+    /// per the solved `tree`. Empty if the position isn't in `tree` or the
+    /// game has already finished.
+    pub(crate) fn hint(&self, tree: &Tree) -> Vec<Action> {
+        match nav::find_node(tree, self.state) {
+            Some((node_id, transform)) => {
+                let inverse = transform.inverse();
+                nav::best_actions(tree, node_id)
+                    .into_iter()
+                    .map(|(action, _)| inverse.apply_to_action(action))
+                    .collect()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solved_tree;
+
+    #[test]
+    fn create_waits_for_opponent_then_join_opens_the_board() {
+        let mut game = Game::create();
+        assert_eq!(game.lifecycle(), State::WaitingForOpponent);
+        assert_eq!(game.join(), State::YourMove);
+        assert_eq!(game.lifecycle(), State::YourMove);
+    }
+
+    #[test]
+    fn make_move_before_join_is_rejected() {
+        let mut game = Game::create();
+        assert_eq!(
+            game.make_move(Player::You, Action { row: 0, col: 0 }),
+            Err(GameError::NotYourTurn)
+        );
+    }
+
+    #[test]
+    fn make_move_out_of_turn_is_rejected() {
+        let mut game = Game::create();
+        game.join();
+        assert_eq!(
+            game.make_move(Player::Opponent, Action { row: 0, col: 0 }),
+            Err(GameError::NotYourTurn)
+        );
+        // The rejected move must not have been applied.
+        assert_eq!(game.lifecycle(), State::YourMove);
+        assert!(game.state().board[0][0].is_none());
+    }
+
+    #[test]
+    fn make_move_onto_an_occupied_cell_is_rejected() {
+        let mut game = Game::create();
+        game.join();
+        assert_eq!(
+            game.make_move(Player::You, Action { row: 0, col: 0 }),
+            Ok(State::OpponentMove)
+        );
+        assert_eq!(
+            game.make_move(Player::Opponent, Action { row: 0, col: 0 }),
+            Err(GameError::InvalidMove)
+        );
+    }
+
+    #[test]
+    fn playing_past_a_finished_game_is_rejected() {
+        let mut game = Game::create();
+        game.join();
+        // You: top row, Opponent: middle row, for a quick forced win.
+        let moves = [
+            (Player::You, Action { row: 0, col: 0 }),
+            (Player::Opponent, Action { row: 1, col: 0 }),
+            (Player::You, Action { row: 0, col: 1 }),
+            (Player::Opponent, Action { row: 1, col: 1 }),
+            (Player::You, Action { row: 0, col: 2 }),
+        ];
+        let mut last = None;
+        for (by, action) in moves {
+            last = Some(game.make_move(by, action).unwrap());
+        }
+        assert_eq!(last, Some(State::YouWon));
+        assert_eq!(
+            game.make_move(Player::Opponent, Action { row: 2, col: 0 }),
+            Err(GameError::GameFinished)
+        );
+    }
+
+    #[test]
+    fn hint_matches_the_solved_tree_for_the_opening_move() {
+        let tree = solved_tree();
+        let mut game = Game::create();
+        game.join();
+        let hints = game.hint(&tree);
+        assert!(!hints.is_empty());
+        assert_eq!(
+            game.make_move(Player::You, hints[0]),
+            Ok(State::OpponentMove)
+        );
+    }
+
+    #[test]
+    fn hint_is_empty_once_the_game_has_finished() {
+        let tree = solved_tree();
+        let mut game = Game::create();
+        game.join();
+        for (by, action) in [
+            (Player::You, Action { row: 0, col: 0 }),
+            (Player::Opponent, Action { row: 1, col: 0 }),
+            (Player::You, Action { row: 0, col: 1 }),
+            (Player::Opponent, Action { row: 1, col: 1 }),
+            (Player::You, Action { row: 0, col: 2 }),
+        ] {
+            game.make_move(by, action).unwrap();
+        }
+        assert_eq!(game.lifecycle(), State::YouWon);
+        assert!(game.hint(&tree).is_empty());
+    }
+}
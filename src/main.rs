@@ -1,10 +1,11 @@
 // For Tic Tac Toe
 
-use std::{
-    cell::RefCell,
-    collections::BTreeMap,
-    rc::{Rc, Weak},
-};
+mod game;
+mod nav;
+mod sgf;
+mod simulate;
+
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
@@ -30,6 +31,13 @@ struct GameState {
 }
 
 impl GameState {
+    fn initial() -> GameState {
+        GameState {
+            board: [[None; 3]; 3],
+            turn: Player::You,
+        }
+    }
+
     fn opposite(&self) -> GameState {
         let mut board = self.board;
         for i in 0..3 {
@@ -48,7 +56,71 @@ struct Action {
     col: usize,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+/// One of the 8 symmetries of the square (the dihedral group D4): the 4
+/// rotations and the 4 axis reflections. Applying a `Transform` to a board
+/// position tells you where that position ends up after the corresponding
+/// geometric operation.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    Transpose,
+    AntiTranspose,
+}
+
+impl Transform {
+    const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::Transpose,
+        Transform::AntiTranspose,
+    ];
+
+    fn apply_to_action(&self, action: Action) -> Action {
+        let (row, col) = (action.row, action.col);
+        let (row, col) = match self {
+            Transform::Identity => (row, col),
+            Transform::Rotate90 => (col, 2 - row),
+            Transform::Rotate180 => (2 - row, 2 - col),
+            Transform::Rotate270 => (2 - col, row),
+            Transform::FlipHorizontal => (2 - row, col),
+            Transform::FlipVertical => (row, 2 - col),
+            Transform::Transpose => (col, row),
+            Transform::AntiTranspose => (2 - col, 2 - row),
+        };
+        Action { row, col }
+    }
+
+    fn apply_to_board(&self, board: [[Option<Player>; 3]; 3]) -> [[Option<Player>; 3]; 3] {
+        let mut result = [[None; 3]; 3];
+        for row in 0..3 {
+            for col in 0..3 {
+                let to = self.apply_to_action(Action { row, col });
+                result[to.row][to.col] = board[row][col];
+            }
+        }
+        result
+    }
+
+    /// The transform that undoes this one.
+    fn inverse(&self) -> Transform {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            other => *other,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 enum GameStateInfo {
     W,   // Forced Win
     WD,  // Never Lose
@@ -125,15 +197,101 @@ impl GameState {
         let turn = self.turn.opposite();
         GameState { board, turn }
     }
+
+    /// The lexicographically smallest `GameState` reachable by applying one
+    /// of the 8 board symmetries, along with the `Transform` that produces
+    /// it from `self`. Used to dedup the solved graph across rotations and
+    /// reflections of the same underlying position.
+    fn canonical(&self) -> (GameState, Transform) {
+        Transform::ALL
+            .iter()
+            .map(|&transform| {
+                let board = transform.apply_to_board(self.board);
+                (
+                    GameState {
+                        board,
+                        turn: self.turn,
+                    },
+                    transform,
+                )
+            })
+            .min_by(|(a, _), (b, _)| a.cmp(b))
+            .unwrap()
+    }
 }
 
+/// Index of a node within a `Tree`'s arena.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize, Deserialize)]
+struct NodeId(usize);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 struct GameStateGraphNode {
     state: GameState,
-    actions_from_here: BTreeMap<Action, Rc<RefCell<GameStateGraphNode>>>,
-    actions_to_here: Vec<Weak<RefCell<GameStateGraphNode>>>,
+    #[serde(with = "action_node_map")]
+    actions_from_here: BTreeMap<Action, NodeId>,
+    actions_to_here: Vec<NodeId>,
     info: Option<GameStateInfo>,
 }
 
+/// JSON objects require string keys, but `Action` isn't one, so
+/// `actions_from_here` serializes as a list of `(Action, NodeId)` pairs
+/// instead of relying on serde's default `BTreeMap` encoding.
+mod action_node_map {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{Action, NodeId};
+
+    pub(super) fn serialize<S>(
+        map: &BTreeMap<Action, NodeId>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        map.iter().collect::<Vec<_>>().serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<BTreeMap<Action, NodeId>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(Action, NodeId)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// Arena holding the whole solved game graph, addressed by `NodeId` instead
+/// of `Rc`/`Weak` handles. Nodes are never removed, so `NodeId`s stay valid
+/// for the lifetime of the `Tree`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Tree {
+    nodes: Vec<GameStateGraphNode>,
+}
+
+impl Tree {
+    fn new() -> Tree {
+        Tree { nodes: Vec::new() }
+    }
+
+    fn push(&mut self, state: GameState) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(GameStateGraphNode {
+            state,
+            actions_from_here: BTreeMap::new(),
+            actions_to_here: Vec::new(),
+            info: None,
+        });
+        id
+    }
+
+    fn node(&self, id: NodeId) -> &GameStateGraphNode {
+        &self.nodes[id.0]
+    }
+}
+
 fn build_info(summary: (Player, bool, bool, bool, bool, bool, bool, bool)) -> GameStateInfo {
     match summary {
         // Forced Win
@@ -162,15 +320,19 @@ fn build_info(summary: (Player, bool, bool, bool, bool, bool, bool, bool)) -> Ga
     }
 }
 
-fn build_info_recursively(node: &Rc<RefCell<GameStateGraphNode>>) {
-    if node.borrow().info.is_some() {
+fn build_info_recursively(tree: &mut Tree, node_id: NodeId) {
+    let node = &tree.nodes[node_id.0];
+    if node.info.is_some() {
         // already built
         return;
     }
-    if node.borrow().state.possible_actions().len() != node.borrow().actions_from_here.len() {
+    if node.state.possible_actions().len() != node.actions_from_here.len() {
         // incomplete
         return;
     }
+    let state = node.state;
+    let next_ids: Vec<NodeId> = node.actions_from_here.values().copied().collect();
+
     let mut complete = true;
     let mut has_w = false;
     let mut has_wd = false;
@@ -179,8 +341,8 @@ fn build_info_recursively(node: &Rc<RefCell<GameStateGraphNode>>) {
     let mut has_d = false;
     let mut has_dl = false;
     let mut has_l = false;
-    for (_, next) in node.borrow().actions_from_here.iter() {
-        match next.borrow().info {
+    for next_id in next_ids.iter() {
+        match tree.nodes[next_id.0].info {
             None => complete = false,
             Some(GameStateInfo::W) => has_w = true,
             Some(GameStateInfo::WD) => has_wd = true,
@@ -195,82 +357,203 @@ fn build_info_recursively(node: &Rc<RefCell<GameStateGraphNode>>) {
         return;
     }
 
-    let progress = node.borrow().state.progress();
+    let progress = state.progress();
     let info = match progress {
         Progress::Ongoing => build_info((
-            node.borrow().state.turn,
-            has_w,
-            has_wd,
-            has_wdl,
-            has_wl,
-            has_d,
-            has_dl,
-            has_l,
+            state.turn, has_w, has_wd, has_wdl, has_wl, has_d, has_dl, has_l,
         )),
         Progress::Draw => GameStateInfo::D,
         Progress::Win(Player::You) => GameStateInfo::W,
         Progress::Win(Player::Opponent) => GameStateInfo::L,
     };
-    node.borrow_mut().info.replace(info);
+    tree.nodes[node_id.0].info = Some(info);
 
-    for previous in node.borrow().actions_to_here.iter() {
-        build_info_recursively(&previous.upgrade().unwrap());
+    let predecessors = tree.nodes[node_id.0].actions_to_here.clone();
+    for previous in predecessors {
+        build_info_recursively(tree, previous);
     }
 }
 
+/// Builds the graph of canonical states reachable from `node_id`, whose
+/// `state` must already be canonical. `map` dedups by canonical state, so
+/// the 8 rotations/reflections of a position collapse onto one node.
 fn build_next_states_recursively(
-    node: &Rc<RefCell<GameStateGraphNode>>,
-    map: &mut BTreeMap<GameState, Rc<RefCell<GameStateGraphNode>>>,
+    tree: &mut Tree,
+    node_id: NodeId,
+    map: &mut BTreeMap<GameState, NodeId>,
 ) {
-    if map.get(&node.borrow().state).is_some() {
+    let state = tree.nodes[node_id.0].state;
+    if map.contains_key(&state) {
         return;
-    } else {
-        map.insert(node.borrow().state, node.clone());
     }
-    let actions = node.borrow().state.possible_actions();
+    map.insert(state, node_id);
+    let actions = state.possible_actions();
     for action in actions.iter() {
-        let next_state = node.borrow().state.next(*action);
-        if let Some(next) = map.get(&next_state) {
-            node.borrow_mut()
+        let (canonical_next, _) = state.next(*action).canonical();
+        if let Some(&next_id) = map.get(&canonical_next) {
+            tree.nodes[node_id.0]
                 .actions_from_here
-                .insert(*action, next.clone());
-            next.borrow_mut().actions_to_here.push(Rc::downgrade(node));
+                .insert(*action, next_id);
+            tree.nodes[next_id.0].actions_to_here.push(node_id);
             continue;
         }
-        let next_node = Rc::new(RefCell::new(GameStateGraphNode {
-            state: next_state,
-            actions_from_here: BTreeMap::new(),
-            actions_to_here: Vec::new(),
-            info: None,
-        }));
-        node.borrow_mut()
+        let next_id = tree.push(canonical_next);
+        tree.nodes[node_id.0]
             .actions_from_here
-            .insert(*action, next_node.clone());
-        next_node
-            .borrow_mut()
-            .actions_to_here
-            .push(Rc::downgrade(node));
-        build_next_states_recursively(&next_node, map);
-    }
-    build_info_recursively(node);
+            .insert(*action, next_id);
+        tree.nodes[next_id.0].actions_to_here.push(node_id);
+        build_next_states_recursively(tree, next_id, map);
+    }
+    build_info_recursively(tree, node_id);
 }
 
 fn main() {
-    let initial_node = Rc::new(RefCell::new(GameStateGraphNode {
-        state: GameState {
-            board: [[None; 3]; 3],
-            turn: Player::You,
-        },
-        actions_from_here: BTreeMap::new(),
-        actions_to_here: Vec::new(),
-        info: None,
-    }));
+    let mut tree = Tree::new();
+    let initial_id = tree.push(GameState::initial());
+    let mut map = BTreeMap::new();
+    build_next_states_recursively(&mut tree, initial_id, &mut map);
+    for (_, &id) in map.iter() {
+        let node = tree.node(id);
+        println!("{:?} - {:?}", node.state, node.info);
+        for &next_id in node.actions_from_here.values() {
+            let next = tree.node(next_id);
+            println!("    {:?} - {:?}", next.state, next.info);
+        }
+    }
+
+    println!("\nmainline:");
+    for (state, action, info) in nav::mainline(&tree, initial_id) {
+        println!("{:?} --{:?}--> ({:?})", state, action, info);
+    }
+
+    println!("\nmainline as SGF:");
+    let sgf_text = sgf::mainline_game_record(&tree, initial_id).to_sgf_string();
+    println!("{sgf_text}");
+    let reloaded = sgf::GameRecord::from_sgf_string(&sgf_text).expect("just-exported SGF parses");
+    println!(
+        "re-imported {} root(s) from that text",
+        reloaded.roots.len()
+    );
+
+    println!("\ncursor walk along the mainline, then back:");
+    let mut cursor = nav::Cursor::new(&tree, GameState::initial());
+    for _ in 0..3 {
+        let Some(&(action, _)) = cursor.variations().first() else {
+            break;
+        };
+        println!(
+            "{:?} (info={:?}) --{:?}-->",
+            cursor.state(),
+            cursor.info(),
+            action
+        );
+        cursor.advance(action).unwrap();
+    }
+    cursor.back().unwrap();
+    println!("stepped back to {:?}", cursor.state());
+
+    println!("\nplayed game, both sides taking the top hint:");
+    let mut match_ = game::Game::create();
+    let mut lifecycle = match_.join();
+    loop {
+        let by = match lifecycle {
+            game::State::YourMove => Player::You,
+            game::State::OpponentMove => Player::Opponent,
+            _ => break,
+        };
+        let Some(&action) = match_.hint(&tree).first() else {
+            break;
+        };
+        lifecycle = match_.make_move(by, action).unwrap();
+        println!(
+            "{:?} plays {:?} -> {:?} ({:?})",
+            by,
+            action,
+            lifecycle,
+            match_.state()
+        );
+    }
+    println!("final lifecycle: {:?}", match_.lifecycle());
+
+    println!("\nself-play over 1000 seeded games:");
+    let stats = simulate::simulate_many(&tree, 1000);
+    println!("{stats:?}");
+}
+
+/// Builds the full solved game tree from the initial position. Shared test
+/// fixture for this module and its siblings, so each test module doesn't
+/// have to re-derive it.
+#[cfg(test)]
+pub(crate) fn solved_tree() -> Tree {
+    let mut tree = Tree::new();
+    let initial_id = tree.push(GameState::initial());
     let mut map = BTreeMap::new();
-    build_next_states_recursively(&initial_node, &mut map);
-    for (_, node) in map.iter() {
-        println!("{:?} - {:?}", node.borrow().state, node.borrow().info);
-        for (_, node) in node.borrow().actions_from_here.iter() {
-            println!("    {:?} - {:?}", node.borrow().state, node.borrow().info);
+    build_next_states_recursively(&mut tree, initial_id, &mut map);
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solved_tree_collapses_to_765_canonical_states_with_a_drawn_root() {
+        let tree = solved_tree();
+        // The textbook count of distinct tic-tac-toe positions under the
+        // board's 8-fold symmetry, once canonicalized.
+        assert_eq!(tree.nodes.len(), 765);
+        assert_eq!(tree.node(NodeId(0)).info, Some(GameStateInfo::DL));
+    }
+
+    #[test]
+    fn tree_round_trips_through_json() {
+        let tree = solved_tree();
+        let json = serde_json::to_string(&tree).unwrap();
+        let restored: Tree = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.nodes.len(), tree.nodes.len());
+        for (original, restored) in tree.nodes.iter().zip(restored.nodes.iter()) {
+            assert_eq!(original.state, restored.state);
+            assert_eq!(original.info, restored.info);
+            assert_eq!(
+                original.actions_from_here.keys().collect::<Vec<_>>(),
+                restored.actions_from_here.keys().collect::<Vec<_>>()
+            );
         }
     }
+
+    #[test]
+    fn canonical_folds_symmetric_openings_onto_the_same_node() {
+        let initial = GameState::initial();
+        // Playing either corner is the same opening up to a reflection.
+        let top_left = initial.next(Action { row: 0, col: 0 });
+        let top_right = initial.next(Action { row: 0, col: 2 });
+        assert_ne!(top_left, top_right);
+        assert_eq!(top_left.canonical().0, top_right.canonical().0);
+
+        let tree = solved_tree();
+        let (left_id, _) = nav::find_node(&tree, top_left).unwrap();
+        let (right_id, _) = nav::find_node(&tree, top_right).unwrap();
+        assert_eq!(left_id, right_id);
+    }
+
+    /// Counts reachable positions without folding by symmetry, as a
+    /// baseline for the canonicalized count below.
+    fn count_unfolded_states(state: GameState, seen: &mut std::collections::BTreeSet<GameState>) {
+        if !seen.insert(state) {
+            return;
+        }
+        for action in state.possible_actions() {
+            count_unfolded_states(state.next(action), seen);
+        }
+    }
+
+    #[test]
+    fn canonicalization_shrinks_the_graph_roughly_eightfold() {
+        let mut unfolded = std::collections::BTreeSet::new();
+        count_unfolded_states(GameState::initial(), &mut unfolded);
+        assert_eq!(unfolded.len(), 5478);
+
+        let tree = solved_tree();
+        assert_eq!(tree.nodes.len(), 765);
+    }
 }
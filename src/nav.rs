@@ -0,0 +1,217 @@
+// Navigation layer over a solved `Tree`: a `mainline()` walk of the
+// optimal line of play, forward/backward stepping between neighbouring
+// states, and a `Cursor` a UI could drive interactively.
+//
+// The tree itself only stores canonical (symmetry-reduced) states, so
+// everything here that accepts or returns a concrete, possibly
+// non-canonical board (`find_node`, `children_of`, `Cursor`) translates
+// actions through the `Transform` that relates that board to its
+// canonical node.
+
+use crate::{Action, GameState, GameStateInfo, NodeId, Player, Transform, Tree};
+
+/// Ranks a `GameStateInfo` from You's perspective: higher is better for You.
+/// `mainline`/`Cursor::advance_to_best` use this to pick the child the side
+/// to move would actually choose (maximize for You, minimize for Opponent).
+fn rank_for_you(info: GameStateInfo) -> i8 {
+    match info {
+        GameStateInfo::W => 5,
+        GameStateInfo::WD => 4,
+        GameStateInfo::D => 3,
+        GameStateInfo::WDL => 2,
+        GameStateInfo::WL => 1,
+        GameStateInfo::DL => 0,
+        GameStateInfo::L => -1,
+    }
+}
+
+fn best_action(tree: &Tree, node_id: NodeId) -> Option<(Action, NodeId)> {
+    best_actions(tree, node_id).into_iter().next()
+}
+
+/// All actions from `node_id` that are equally optimal for the side to
+/// move, per `rank_for_you` (maximized by You, minimized by Opponent).
+/// Empty if the node is terminal or not fully solved yet. Actions and the
+/// node id returned are in `node_id`'s own canonical space.
+pub(crate) fn best_actions(tree: &Tree, node_id: NodeId) -> Vec<(Action, NodeId)> {
+    let node = tree.node(node_id);
+    let mover = node.state.turn;
+    let mover_rank = |rank: i8| match mover {
+        Player::You => rank,
+        Player::Opponent => -rank,
+    };
+    let scored: Vec<(Action, NodeId, i8)> = node
+        .actions_from_here
+        .iter()
+        .filter_map(|(&action, &next_id)| {
+            tree.node(next_id)
+                .info
+                .map(|info| (action, next_id, mover_rank(rank_for_you(info))))
+        })
+        .collect();
+    let Some(best) = scored.iter().map(|&(_, _, rank)| rank).max() else {
+        return Vec::new();
+    };
+    scored
+        .into_iter()
+        .filter(|&(_, _, rank)| rank == best)
+        .map(|(action, next_id, _)| (action, next_id))
+        .collect()
+}
+
+/// Finds the node holding the canonical form of `state`, if `tree`
+/// contains it, along with the `Transform` mapping `state`'s own action
+/// space onto that node's.
+pub(crate) fn find_node(tree: &Tree, state: GameState) -> Option<(NodeId, Transform)> {
+    let (canonical, transform) = state.canonical();
+    tree.nodes
+        .iter()
+        .position(|n| n.state == canonical)
+        .map(|index| (NodeId(index), transform))
+}
+
+/// Yields `(state, action, info)` along the optimal line of play from the
+/// given root, stopping once a terminal node (or an unsolved one) is
+/// reached. `info` is the classification of `state`, the position the
+/// action is chosen from.
+pub(crate) struct Mainline<'a> {
+    tree: &'a Tree,
+    current: Option<NodeId>,
+}
+
+impl<'a> Iterator for Mainline<'a> {
+    type Item = (GameState, Action, GameStateInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node_id = self.current?;
+        let node = self.tree.node(node_id);
+        let info = node.info?;
+        let (action, next_id) = best_action(self.tree, node_id)?;
+        self.current = Some(next_id);
+        Some((node.state, action, info))
+    }
+}
+
+/// Walks the principal variation starting from `root`.
+pub(crate) fn mainline(tree: &Tree, root: NodeId) -> Mainline<'_> {
+    Mainline {
+        tree,
+        current: Some(root),
+    }
+}
+
+/// All legal continuations from `state`, if its canonical form is present
+/// in `tree`. Actions and resulting states are in `state`'s own
+/// orientation, not the tree's canonical one.
+pub(crate) fn children_of(tree: &Tree, state: GameState) -> Vec<(Action, GameState)> {
+    let Some((node_id, transform)) = find_node(tree, state) else {
+        return Vec::new();
+    };
+    let inverse = transform.inverse();
+    tree.node(node_id)
+        .actions_from_here
+        .keys()
+        .map(|&canonical_action| {
+            let action = inverse.apply_to_action(canonical_action);
+            (action, state.next(action))
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub(crate) enum NavError {
+    NoSuchAction,
+    AtRoot,
+}
+
+/// A movable position within a solved `Tree`, for interactively browsing
+/// the full solution rather than just its mainline. Tracks a concrete
+/// board in the orientation the caller actually sees, translating through
+/// `find_node`'s `Transform` as needed. Visited states are kept on `history`
+/// so `back()` undoes exactly the move `advance()` made, rather than
+/// landing on the tree's (possibly reoriented) canonical predecessor.
+pub(crate) struct Cursor<'a> {
+    tree: &'a Tree,
+    state: GameState,
+    history: Vec<GameState>,
+}
+
+impl<'a> Cursor<'a> {
+    pub(crate) fn new(tree: &'a Tree, state: GameState) -> Cursor<'a> {
+        Cursor {
+            tree,
+            state,
+            history: Vec::new(),
+        }
+    }
+
+    pub(crate) fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub(crate) fn info(&self) -> Option<GameStateInfo> {
+        find_node(self.tree, self.state).and_then(|(id, _)| self.tree.node(id).info)
+    }
+
+    /// Steps forward by playing `action`, given in the cursor's own
+    /// orientation.
+    pub(crate) fn advance(&mut self, action: Action) -> Result<(), NavError> {
+        let (node_id, transform) =
+            find_node(self.tree, self.state).ok_or(NavError::NoSuchAction)?;
+        let canonical_action = transform.apply_to_action(action);
+        if !self
+            .tree
+            .node(node_id)
+            .actions_from_here
+            .contains_key(&canonical_action)
+        {
+            return Err(NavError::NoSuchAction);
+        }
+        self.history.push(self.state);
+        self.state = self.state.next(action);
+        Ok(())
+    }
+
+    /// Undoes the last `advance()`, returning to the exact board the
+    /// cursor was at before it (not just a symmetric equivalent).
+    pub(crate) fn back(&mut self) -> Result<(), NavError> {
+        self.state = self.history.pop().ok_or(NavError::AtRoot)?;
+        Ok(())
+    }
+
+    /// All actions available from the current position, alongside the
+    /// state each leads to, in the cursor's own orientation.
+    pub(crate) fn variations(&self) -> Vec<(Action, GameState)> {
+        children_of(self.tree, self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solved_tree;
+
+    #[test]
+    fn back_undoes_advance_to_the_exact_prior_state() {
+        let tree = solved_tree();
+        let initial = GameState::initial();
+        let mut cursor = Cursor::new(&tree, initial);
+
+        let (action, _) = cursor.variations()[0];
+        cursor.advance(action).unwrap();
+        assert_ne!(cursor.state(), initial);
+
+        cursor.back().unwrap();
+        // Must land on the exact board played from, not a symmetric
+        // equivalent from the tree's canonical form (the bug fixed in the
+        // commit that added `history`-based undo).
+        assert_eq!(cursor.state(), initial);
+    }
+
+    #[test]
+    fn back_at_the_root_is_an_error() {
+        let tree = solved_tree();
+        let mut cursor = Cursor::new(&tree, GameState::initial());
+        assert!(matches!(cursor.back(), Err(NavError::AtRoot)));
+    }
+}
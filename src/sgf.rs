@@ -0,0 +1,336 @@
+// SGF-style (Smart Game Format) move-tree export/import.
+//
+// A `GameRecord` is an ordered forest of `GameRecordNode`s: each node is a
+// single move plus optional annotation, and a node's children are the
+// variations that can follow it. A straight line with no branching is just
+// a chain of single-child nodes; a branch point is a node with more than
+// one child.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::{nav, Action, GameStateInfo, NodeId, Player, Tree};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GameRecordNode {
+    pub(crate) player: Player,
+    pub(crate) action: Action,
+    pub(crate) info: Option<GameStateInfo>,
+    pub(crate) children: Vec<GameRecordNode>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct GameRecord {
+    pub(crate) roots: Vec<GameRecordNode>,
+}
+
+#[derive(Debug)]
+pub(crate) enum SgfError {
+    Malformed(String),
+}
+
+impl fmt::Display for SgfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SgfError::Malformed(reason) => write!(f, "malformed SGF: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SgfError {}
+
+impl GameRecord {
+    pub(crate) fn to_sgf_string(&self) -> String {
+        let mut out = String::new();
+        for root in &self.roots {
+            out.push('(');
+            write_node(root, &mut out);
+            out.push(')');
+        }
+        out
+    }
+
+    pub(crate) fn from_sgf_string(sgf: &str) -> Result<GameRecord, SgfError> {
+        let mut parser = Parser::new(sgf);
+        let mut roots = Vec::new();
+        loop {
+            parser.skip_ws();
+            if parser.chars.peek().is_none() {
+                break;
+            }
+            roots.push(parser.parse_game_tree()?);
+        }
+        Ok(GameRecord { roots })
+    }
+}
+
+/// Exports the principal variation from `root` in a solved `tree` as a
+/// single-line `GameRecord`: one node per move, each annotated with the
+/// classification of the position it was played from.
+pub(crate) fn mainline_game_record(tree: &Tree, root: NodeId) -> GameRecord {
+    let moves: Vec<(Player, Action, GameStateInfo)> = nav::mainline(tree, root)
+        .map(|(state, action, info)| (state.turn, action, info))
+        .collect();
+    let mut chain: Vec<GameRecordNode> = Vec::new();
+    for (player, action, info) in moves.into_iter().rev() {
+        chain = vec![GameRecordNode {
+            player,
+            action,
+            info: Some(info),
+            children: chain,
+        }];
+    }
+    GameRecord { roots: chain }
+}
+
+fn write_node(node: &GameRecordNode, out: &mut String) {
+    out.push(';');
+    out.push(match node.player {
+        Player::You => 'B',
+        Player::Opponent => 'W',
+    });
+    out.push('[');
+    out.push_str(&node.action.row.to_string());
+    out.push_str(&node.action.col.to_string());
+    out.push(']');
+    if let Some(info) = node.info {
+        out.push_str("C[");
+        out.push_str(&format!("{info:?}"));
+        out.push(']');
+    }
+    match node.children.as_slice() {
+        [] => {}
+        [only] => write_node(only, out),
+        many => {
+            for child in many {
+                out.push('(');
+                write_node(child, out);
+                out.push(')');
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(sgf: &'a str) -> Parser<'a> {
+        Parser {
+            chars: sgf.chars().peekable(),
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.chars.peek(), Some(c) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), SgfError> {
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            other => Err(SgfError::Malformed(format!(
+                "expected '{expected}', found {other:?}"
+            ))),
+        }
+    }
+
+    fn parse_game_tree(&mut self) -> Result<GameRecordNode, SgfError> {
+        self.skip_ws();
+        self.expect('(')?;
+        let head = self
+            .parse_sequence()?
+            .ok_or_else(|| SgfError::Malformed("game tree with no nodes".into()))?;
+        self.skip_ws();
+        self.expect(')')?;
+        Ok(head)
+    }
+
+    /// Parses a straight-line chain of `;`-nodes, then attaches either the
+    /// rest of the chain (single following node) or a set of `(...)`
+    /// variation subtrees to the last node's `children`.
+    fn parse_sequence(&mut self) -> Result<Option<GameRecordNode>, SgfError> {
+        self.skip_ws();
+        if self.chars.peek() != Some(&';') {
+            return Ok(None);
+        }
+        self.chars.next();
+        let mut node = self.parse_node_properties()?;
+        self.skip_ws();
+        if self.chars.peek() == Some(&';') {
+            if let Some(rest) = self.parse_sequence()? {
+                node.children.push(rest);
+            }
+        } else {
+            while self.chars.peek() == Some(&'(') {
+                node.children.push(self.parse_game_tree()?);
+                self.skip_ws();
+            }
+        }
+        Ok(Some(node))
+    }
+
+    fn parse_node_properties(&mut self) -> Result<GameRecordNode, SgfError> {
+        let player = match self.chars.next() {
+            Some('B') => Player::You,
+            Some('W') => Player::Opponent,
+            other => {
+                return Err(SgfError::Malformed(format!(
+                    "expected move color 'B' or 'W', found {other:?}"
+                )))
+            }
+        };
+        self.expect('[')?;
+        let row = self.parse_coordinate()?;
+        let col = self.parse_coordinate()?;
+        self.expect(']')?;
+        self.skip_ws();
+        let mut info = None;
+        if self.chars.peek() == Some(&'C') {
+            self.chars.next();
+            self.expect('[')?;
+            let text = self.take_until(']')?;
+            info = Some(parse_game_state_info(&text)?);
+        }
+        Ok(GameRecordNode {
+            player,
+            action: Action { row, col },
+            info,
+            children: Vec::new(),
+        })
+    }
+
+    fn parse_digit(&mut self) -> Result<usize, SgfError> {
+        match self.chars.next() {
+            Some(c) if c.is_ascii_digit() => Ok(c as usize - '0' as usize),
+            other => Err(SgfError::Malformed(format!(
+                "expected a digit, found {other:?}"
+            ))),
+        }
+    }
+
+    /// A row or column digit, bounds-checked against the 3x3 board so an
+    /// out-of-range coordinate is rejected here rather than panicking the
+    /// moment it's later indexed into a board.
+    fn parse_coordinate(&mut self) -> Result<usize, SgfError> {
+        let digit = self.parse_digit()?;
+        if digit >= 3 {
+            return Err(SgfError::Malformed(format!(
+                "coordinate {digit} is out of range for a 3x3 board"
+            )));
+        }
+        Ok(digit)
+    }
+
+    fn take_until(&mut self, terminator: char) -> Result<String, SgfError> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                Some(c) if c == terminator => return Ok(text),
+                Some(c) => text.push(c),
+                None => return Err(SgfError::Malformed("unterminated property value".into())),
+            }
+        }
+    }
+}
+
+fn parse_game_state_info(text: &str) -> Result<GameStateInfo, SgfError> {
+    match text {
+        "W" => Ok(GameStateInfo::W),
+        "WD" => Ok(GameStateInfo::WD),
+        "WDL" => Ok(GameStateInfo::WDL),
+        "WL" => Ok(GameStateInfo::WL),
+        "D" => Ok(GameStateInfo::D),
+        "DL" => Ok(GameStateInfo::DL),
+        "L" => Ok(GameStateInfo::L),
+        other => Err(SgfError::Malformed(format!(
+            "unknown GameStateInfo annotation {other:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solved_tree;
+    use crate::GameState;
+
+    fn sample_record() -> GameRecord {
+        GameRecord {
+            roots: vec![GameRecordNode {
+                player: Player::You,
+                action: Action { row: 2, col: 2 },
+                info: None,
+                children: vec![GameRecordNode {
+                    player: Player::Opponent,
+                    action: Action { row: 0, col: 0 },
+                    info: Some(GameStateInfo::L),
+                    children: vec![GameRecordNode {
+                        player: Player::You,
+                        action: Action { row: 1, col: 1 },
+                        info: Some(GameStateInfo::W),
+                        children: Vec::new(),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn round_trips_through_sgf_text() {
+        let record = sample_record();
+        let sgf = record.to_sgf_string();
+        assert_eq!(sgf, "(;B[22];W[00]C[L];B[11]C[W])");
+        let parsed = GameRecord::from_sgf_string(&sgf).unwrap();
+        assert_eq!(parsed, record);
+    }
+
+    #[test]
+    fn mainline_game_record_matches_the_solved_tree() {
+        let tree = solved_tree();
+        let initial_id = nav::find_node(&tree, GameState::initial()).unwrap().0;
+        let expected: Vec<(Player, Action, GameStateInfo)> = nav::mainline(&tree, initial_id)
+            .map(|(state, action, info)| (state.turn, action, info))
+            .collect();
+
+        let record = mainline_game_record(&tree, initial_id);
+        assert_eq!(record.roots.len(), 1);
+
+        let mut node = &record.roots[0];
+        for (player, action, info) in &expected {
+            assert_eq!(node.player, *player);
+            assert_eq!(node.action, *action);
+            assert_eq!(node.info, Some(*info));
+            match node.children.as_slice() {
+                [] => break,
+                [only] => node = only,
+                _ => panic!("mainline export should not branch"),
+            }
+        }
+
+        // Round-tripping the export through SGF text must reproduce it.
+        let sgf = record.to_sgf_string();
+        assert_eq!(GameRecord::from_sgf_string(&sgf).unwrap(), record);
+    }
+
+    #[test]
+    fn from_sgf_string_rejects_an_unterminated_annotation() {
+        let err = GameRecord::from_sgf_string("(;B[22]C[L)").unwrap_err();
+        assert!(matches!(err, SgfError::Malformed(_)));
+    }
+
+    #[test]
+    fn from_sgf_string_rejects_a_mismatched_paren() {
+        let err = GameRecord::from_sgf_string("(;B[22]").unwrap_err();
+        assert!(matches!(err, SgfError::Malformed(_)));
+    }
+
+    #[test]
+    fn from_sgf_string_rejects_an_out_of_range_coordinate() {
+        let err = GameRecord::from_sgf_string("(;B[93])").unwrap_err();
+        assert!(matches!(err, SgfError::Malformed(_)));
+    }
+}
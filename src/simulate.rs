@@ -0,0 +1,93 @@
+// Seeded self-play: reproducibly plays out whole games by choosing
+// uniformly among the moves that preserve the best available
+// `GameStateInfo`, so play stays optimal but varies across seeds.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{nav, GameState, Player, Progress, Tree};
+
+/// Aggregate outcome tallies from You's perspective.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Stats {
+    pub(crate) wins: u32,
+    pub(crate) draws: u32,
+    pub(crate) losses: u32,
+}
+
+/// Plays one complete game of fully optimal play from `seed`.
+pub(crate) fn simulate(tree: &Tree, seed: u64) -> Progress {
+    simulate_with(tree, seed, None)
+}
+
+/// Plays one complete game from `seed`, where `random_player` (if any)
+/// chooses uniformly among *all* legal moves rather than only the
+/// best-ranked ones. Useful for checking that optimal play never loses
+/// even against an unpredictable opponent.
+pub(crate) fn simulate_with(tree: &Tree, seed: u64, random_player: Option<Player>) -> Progress {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut state = GameState::initial();
+    loop {
+        let progress = state.progress();
+        if !matches!(progress, Progress::Ongoing) {
+            return progress;
+        }
+
+        let action = if random_player == Some(state.turn) {
+            let actions = state.possible_actions();
+            actions[rng.gen_range(0..actions.len())]
+        } else {
+            let (node_id, transform) =
+                nav::find_node(tree, state).expect("reachable state missing from solved tree");
+            let choices = nav::best_actions(tree, node_id);
+            let canonical_action = choices[rng.gen_range(0..choices.len())].0;
+            transform.inverse().apply_to_action(canonical_action)
+        };
+        state = state.next(action);
+    }
+}
+
+/// Runs `simulate` for seeds `0..count` and tallies the outcomes.
+pub(crate) fn simulate_many(tree: &Tree, count: u64) -> Stats {
+    let mut stats = Stats::default();
+    for seed in 0..count {
+        match simulate(tree, seed) {
+            Progress::Win(Player::You) => stats.wins += 1,
+            Progress::Win(Player::Opponent) => stats.losses += 1,
+            Progress::Draw => stats.draws += 1,
+            Progress::Ongoing => unreachable!("simulate only returns terminal outcomes"),
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solved_tree;
+
+    #[test]
+    fn perfect_play_always_draws() {
+        let tree = solved_tree();
+        for seed in 0..50 {
+            assert!(
+                matches!(simulate(&tree, seed), Progress::Draw),
+                "seed {seed} did not end in a draw under perfect play"
+            );
+        }
+    }
+
+    #[test]
+    fn random_opponent_never_beats_perfect_play() {
+        let tree = solved_tree();
+        for seed in 0..50 {
+            assert!(
+                !matches!(
+                    simulate_with(&tree, seed, Some(Player::Opponent)),
+                    Progress::Win(Player::Opponent)
+                ),
+                "seed {seed}: perfect play lost to a random opponent"
+            );
+        }
+    }
+}